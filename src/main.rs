@@ -2,12 +2,16 @@ use std::{
     env,
     error::Error,
     fmt,
-    io::{self, BufRead, StdinLock},
+    io::{self, BufRead, StdinLock, Write},
     process::ExitCode,
 };
 
+use flate2::read::MultiGzDecoder;
 use slug::slugify;
 
+/// Magic bytes at the start of a gzip stream (RFC 1952).
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
 #[derive(Debug)]
 struct SimpleError(String);
 
@@ -30,7 +34,7 @@ enum Operation {
     Uppercase,
     NoSpaces,
     Slugify,
-    Csv,
+    Csv(CsvOptions),
 }
 
 impl TryFrom<&str> for Operation {
@@ -45,8 +49,6 @@ impl TryFrom<&str> for Operation {
             Ok(Operation::NoSpaces)
         } else if value == "slugify" {
             Ok(Operation::Slugify)
-        } else if value == "csv" {
-            Ok(Operation::Csv)
         } else {
             Err(format!("Unrecognized operation: {}", value))
         }
@@ -60,23 +62,209 @@ impl fmt::Display for Operation {
             Self::Uppercase => write!(f, "uppercase"),
             Self::NoSpaces => write!(f, "no-spaces"),
             Self::Slugify => write!(f, "slugify"),
-            Self::Csv => write!(f, "csv"),
+            Self::Csv(_) => write!(f, "csv"),
+        }
+    }
+}
+
+/// A column reference in a `csv` CLI option: either the column's title or
+/// its zero-based position in the header.
+enum ColumnSpec {
+    Name(String),
+    Index(usize),
+}
+
+impl From<&str> for ColumnSpec {
+    fn from(value: &str) -> Self {
+        match value.parse::<usize>() {
+            Ok(index) => Self::Index(index),
+            Err(_) => Self::Name(value.to_owned()),
+        }
+    }
+}
+
+/// One key of a `--sort` option: a column plus its direction, `-column`
+/// meaning descending.
+struct SortKey {
+    column: ColumnSpec,
+    descending: bool,
+}
+
+impl SortKey {
+    fn parse(value: &str) -> Result<Self, String> {
+        match value.strip_prefix('-') {
+            Some("") => Err("Empty sort column name.".to_owned()),
+            Some(name) => Ok(Self {
+                column: ColumnSpec::from(name),
+                descending: true,
+            }),
+            None => Ok(Self {
+                column: ColumnSpec::from(value),
+                descending: false,
+            }),
+        }
+    }
+}
+
+enum Comparison {
+    Eq,
+    Lt,
+    Gt,
+}
+
+/// A `--filter` expression such as `age>30` or `name=foo`.
+struct Predicate {
+    column: ColumnSpec,
+    comparison: Comparison,
+    value: String,
+}
+
+impl Predicate {
+    fn parse(expr: &str) -> Result<Self, String> {
+        for (token, comparison) in [(">", Comparison::Gt), ("<", Comparison::Lt), ("=", Comparison::Eq)] {
+            if let Some((column, value)) = expr.split_once(token) {
+                if column.is_empty() {
+                    return Err(format!("Missing column name in filter expression: {}", expr));
+                }
+                return Ok(Self {
+                    column: ColumnSpec::from(column),
+                    comparison,
+                    value: value.to_owned(),
+                });
+            }
+        }
+        Err(format!("Unrecognized filter expression: {}", expr))
+    }
+
+    fn matches(&self, value: &str) -> bool {
+        match Column::compare(value, &self.value) {
+            std::cmp::Ordering::Equal => matches!(self.comparison, Comparison::Eq),
+            std::cmp::Ordering::Less => matches!(self.comparison, Comparison::Lt),
+            std::cmp::Ordering::Greater => matches!(self.comparison, Comparison::Gt),
+        }
+    }
+}
+
+/// How a `Csv` table is rendered to text.
+enum OutputFormat {
+    /// The original ASCII box table with `|`/`-` borders.
+    Box,
+    /// Fields re-emitted joined by the configured delimiter, one record per
+    /// line.
+    Plain,
+    /// A Markdown table: header row, `---` separator, then data rows.
+    Markdown,
+}
+
+impl OutputFormat {
+    fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "box" => Ok(Self::Box),
+            "plain" => Ok(Self::Plain),
+            "markdown" => Ok(Self::Markdown),
+            _ => Err(format!("Unrecognized output format: {}", value)),
+        }
+    }
+}
+
+/// Column selection, sorting and filtering options for the `csv` operation,
+/// parsed from `--select=`, `--sort=`, `--filter=`, `--delimiter=` and
+/// `--format=` CLI flags.
+struct CsvOptions {
+    select: Option<Vec<ColumnSpec>>,
+    sort: Option<Vec<SortKey>>,
+    filter: Option<Predicate>,
+    delimiter: char,
+    format: OutputFormat,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            select: None,
+            sort: None,
+            filter: None,
+            delimiter: ',',
+            format: OutputFormat::Box,
+        }
+    }
+}
+
+impl CsvOptions {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let mut options = Self::default();
+
+        for arg in args {
+            let (flag, value) = arg
+                .split_once('=')
+                .ok_or_else(|| format!("Invalid csv option: {}", arg))?;
+
+            match flag {
+                "--select" => {
+                    options.select = Some(value.split(',').map(ColumnSpec::from).collect());
+                }
+                "--sort" => {
+                    options.sort = Some(
+                        value
+                            .split(',')
+                            .map(SortKey::parse)
+                            .collect::<Result<Vec<_>, _>>()?,
+                    );
+                }
+                "--filter" => {
+                    options.filter = Some(Predicate::parse(value)?);
+                }
+                "--delimiter" => {
+                    options.delimiter = Self::parse_delimiter(value)?;
+                }
+                "--format" => {
+                    options.format = OutputFormat::parse(value)?;
+                }
+                _ => return Err(format!("Unrecognized csv option: {}", flag)),
+            }
+        }
+
+        Ok(options)
+    }
+
+    fn parse_delimiter(value: &str) -> Result<char, String> {
+        if value == "\\t" {
+            return Ok('\t');
+        }
+
+        let mut chars = value.chars();
+        match (chars.next(), chars.next()) {
+            (Some(delimiter), None) => Ok(delimiter),
+            _ => Err(format!("Delimiter must be a single character: {}", value)),
         }
     }
 }
 
 struct Reader<'a> {
-    input: StdinLock<'a>,
+    input: Box<dyn BufRead + 'a>,
     buf: String,
 }
 
 impl<'a> Reader<'a> {
     fn stdin() -> Self {
         Self {
-            input: io::stdin().lock(),
+            input: Self::detect_compression(io::stdin().lock()),
             buf: String::new(),
         }
     }
+
+    /// Peeks the first two bytes of `input` for the gzip magic and, if
+    /// found, wraps it in a streaming `MultiGzDecoder` so multi-member
+    /// `.gz` archives piped into the tool are transparently decompressed.
+    /// Otherwise the bytes are passed through unchanged.
+    fn detect_compression(mut input: StdinLock<'a>) -> Box<dyn BufRead + 'a> {
+        let is_gzip = matches!(input.fill_buf(), Ok(peek) if peek.starts_with(&GZIP_MAGIC));
+        if is_gzip {
+            Box::new(io::BufReader::new(MultiGzDecoder::new(input)))
+        } else {
+            Box::new(input)
+        }
+    }
 }
 
 impl<'a> Iterator for Reader<'a> {
@@ -90,7 +278,109 @@ impl<'a> Iterator for Reader<'a> {
         if len == 0 {
             return None;
         }
-        Some(self.buf[..len - 1].to_string())
+        let line = self.buf.strip_suffix('\n').unwrap_or(&self.buf);
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        Some(line.to_string())
+    }
+}
+
+/// A byte-level scanner that tokenizes RFC 4180 CSV records out of a
+/// `BufRead`, so quoted fields, embedded delimiters and quoted newlines are
+/// handled correctly instead of splitting on `,` one line at a time.
+struct CsvScanner<'a, R: BufRead> {
+    input: &'a mut R,
+    delimiter: u8,
+    pending: Option<u8>,
+    record_no: usize,
+}
+
+impl<'a, R: BufRead> CsvScanner<'a, R> {
+    fn new(input: &'a mut R, delimiter: u8) -> Self {
+        Self {
+            input,
+            delimiter,
+            pending: None,
+            record_no: 0,
+        }
+    }
+
+    fn read_byte(&mut self) -> io::Result<Option<u8>> {
+        if let Some(byte) = self.pending.take() {
+            return Ok(Some(byte));
+        }
+
+        let mut buf = [0u8; 1];
+        loop {
+            return match self.input.read(&mut buf) {
+                Ok(0) => Ok(None),
+                Ok(_) => Ok(Some(buf[0])),
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => Err(e),
+            };
+        }
+    }
+
+    fn unread_byte(&mut self, byte: u8) {
+        self.pending = Some(byte);
+    }
+
+    /// Reads the next CSV record, returning `None` at true end of input and
+    /// an error if a quoted field is left unterminated at EOF.
+    fn next_record(&mut self) -> Result<Option<Vec<String>>, Box<dyn Error>> {
+        let mut fields = Vec::new();
+        let mut field = Vec::new();
+        let mut quoted = false;
+        let mut started = false;
+
+        loop {
+            let Some(byte) = self.read_byte()? else {
+                if quoted {
+                    return Err(Box::new(SimpleError(format!(
+                        "Record {}: unterminated quote.",
+                        self.record_no
+                    ))));
+                }
+                if !started {
+                    return Ok(None);
+                }
+                fields.push(String::from_utf8(field)?);
+                return Ok(Some(fields));
+            };
+
+            if !started {
+                started = true;
+                self.record_no += 1;
+            }
+
+            if quoted {
+                if byte == b'"' {
+                    match self.read_byte()? {
+                        Some(b'"') => field.push(b'"'),
+                        Some(next) => {
+                            self.unread_byte(next);
+                            quoted = false;
+                        }
+                        None => quoted = false,
+                    }
+                } else {
+                    field.push(byte);
+                }
+                continue;
+            }
+
+            if byte == b'"' && field.is_empty() {
+                quoted = true;
+            } else if byte == self.delimiter {
+                fields.push(String::from_utf8(std::mem::take(&mut field))?);
+            } else if byte == b'\r' {
+                // ignored so CRLF line endings behave like LF
+            } else if byte == b'\n' {
+                fields.push(String::from_utf8(std::mem::take(&mut field))?);
+                return Ok(Some(fields));
+            } else {
+                field.push(byte);
+            }
+        }
     }
 }
 
@@ -99,26 +389,29 @@ struct Csv {
 }
 
 impl Csv {
-    fn from_reader(reader: &mut Reader) -> Result<Self, Box<dyn Error>> {
-        let Some(header) = reader.next() else {
+    fn from_reader(reader: &mut Reader, delimiter: char) -> Result<Self, Box<dyn Error>> {
+        let delimiter = u8::try_from(delimiter)
+            .map_err(|_| SimpleError(format!("Non-ASCII delimiter: {:?}", delimiter)))?;
+        let mut scanner = CsvScanner::new(&mut reader.input, delimiter);
+
+        let Some(header) = scanner.next_record()? else {
             return Err(Box::new(SimpleError::from_str("Empty CSV given.")));
         };
 
-        let mut columns: Vec<Column> = header.split(',').map(Column::from_title).collect();
+        let mut columns: Vec<Column> = header.iter().map(|t| Column::from_title(t)).collect();
         if columns.is_empty() {
             return Err(Box::new(SimpleError::from_str("Empty header given.")));
         }
 
-        for (i, line) in reader.enumerate() {
-            let mut values: Vec<String> = line.split(',').map(String::from).collect();
+        while let Some(values) = scanner.next_record()? {
             if values.len() != columns.len() {
                 return Err(Box::new(SimpleError(format!(
-                    "Line {}: invalid number of values.",
-                    i + 2
+                    "Record {}: invalid number of values.",
+                    scanner.record_no
                 ))));
             }
 
-            for (column, value) in columns.iter_mut().zip(values.drain(..)) {
+            for (column, value) in columns.iter_mut().zip(values) {
                 column.append(value);
             }
         }
@@ -150,6 +443,116 @@ impl Csv {
     fn num_rows(&self) -> usize {
         self.columns[0].len()
     }
+
+    fn column_index(&self, spec: &ColumnSpec) -> Result<usize, Box<dyn Error>> {
+        match spec {
+            ColumnSpec::Index(index) if *index < self.columns.len() => Ok(*index),
+            ColumnSpec::Index(index) => Err(Box::new(SimpleError(format!(
+                "Column index out of range: {}",
+                index
+            )))),
+            ColumnSpec::Name(name) => self
+                .columns
+                .iter()
+                .position(|column| &column.values[0] == name)
+                .ok_or_else(|| Box::new(SimpleError(format!("Unknown column: {}", name))) as _),
+        }
+    }
+
+    /// Projects the table down to the given columns, in the given order.
+    fn select(&self, specs: &[ColumnSpec]) -> Result<Self, Box<dyn Error>> {
+        let mut columns = Vec::with_capacity(specs.len());
+        for spec in specs {
+            columns.push(self.columns[self.column_index(spec)?].clone());
+        }
+        Ok(Self { columns })
+    }
+
+    /// Reorders the rows of every column consistently by the given sort
+    /// keys, in priority order.
+    fn sort_by(&mut self, keys: &[SortKey]) -> Result<(), Box<dyn Error>> {
+        let mut indices = Vec::with_capacity(keys.len());
+        for key in keys {
+            indices.push((self.column_index(&key.column)?, key.descending));
+        }
+
+        let mut order: Vec<usize> = (1..self.num_rows()).collect();
+        order.sort_by(|&a, &b| {
+            for &(index, descending) in &indices {
+                let values = &self.columns[index].values;
+                let ordering = Column::compare(&values[a], &values[b]);
+                let ordering = if descending { ordering.reverse() } else { ordering };
+                if ordering != std::cmp::Ordering::Equal {
+                    return ordering;
+                }
+            }
+            std::cmp::Ordering::Equal
+        });
+
+        for column in &mut self.columns {
+            column.reorder(&order);
+        }
+
+        Ok(())
+    }
+
+    /// Keeps only the rows whose value in `predicate`'s column satisfies it.
+    fn filter(&mut self, predicate: &Predicate) -> Result<(), Box<dyn Error>> {
+        let index = self.column_index(&predicate.column)?;
+        let keep: Vec<usize> = (1..self.num_rows())
+            .filter(|&i| predicate.matches(&self.columns[index].values[i]))
+            .collect();
+
+        for column in &mut self.columns {
+            column.reorder(&keep);
+        }
+
+        Ok(())
+    }
+
+    /// Renders the table in the requested `OutputFormat`, re-joining plain
+    /// output with `delimiter`.
+    fn render(&self, format: &OutputFormat, delimiter: char) -> String {
+        match format {
+            OutputFormat::Box => self.to_string(),
+            OutputFormat::Plain => self.render_plain(delimiter),
+            OutputFormat::Markdown => self.render_markdown(),
+        }
+    }
+
+    fn render_plain(&self, delimiter: char) -> String {
+        let mut out = String::new();
+        for i in 0..self.num_rows() {
+            let row: Vec<&str> = self.columns.iter().map(|c| c.values[i].as_str()).collect();
+            out.push_str(&row.join(&delimiter.to_string()));
+            out.push('\n');
+        }
+        out
+    }
+
+    fn render_markdown(&self) -> String {
+        let mut out = String::new();
+        out.push_str(&self.markdown_row(0));
+        out.push_str(&self.markdown_separator());
+        for i in 1..self.num_rows() {
+            out.push_str(&self.markdown_row(i));
+        }
+        out
+    }
+
+    fn markdown_row(&self, index: usize) -> String {
+        let cells: Vec<String> = self
+            .columns
+            .iter()
+            .map(|c| format!("{:<width$}", c.values[index], width = c.width))
+            .collect();
+        format!("| {} |\n", cells.join(" | "))
+    }
+
+    fn markdown_separator(&self) -> String {
+        let cells: Vec<String> = self.columns.iter().map(|c| "-".repeat(c.width)).collect();
+        format!("| {} |\n", cells.join(" | "))
+    }
 }
 
 impl fmt::Display for Csv {
@@ -169,6 +572,7 @@ impl fmt::Display for Csv {
     }
 }
 
+#[derive(Clone)]
 struct Column {
     width: usize,
     values: Vec<String>,
@@ -194,6 +598,24 @@ impl Column {
     fn format(&self, index: usize, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "| {:<width$} ", &self.values[index], width = self.width)
     }
+
+    /// Keeps the header (row 0) and the data rows at the given indices, in
+    /// that order, dropping the rest.
+    fn reorder(&mut self, row_indices: &[usize]) {
+        let mut values = Vec::with_capacity(row_indices.len() + 1);
+        values.push(self.values[0].clone());
+        values.extend(row_indices.iter().map(|&i| self.values[i].clone()));
+        self.values = values;
+    }
+
+    /// Orders two values numerically if both parse as numbers, falling back
+    /// to lexical ordering otherwise.
+    fn compare(a: &str, b: &str) -> std::cmp::Ordering {
+        match (a.parse::<f64>(), b.parse::<f64>()) {
+            (Ok(a), Ok(b)) => a.partial_cmp(&b).unwrap_or(std::cmp::Ordering::Equal),
+            _ => a.cmp(b),
+        }
+    }
 }
 
 fn main() -> ExitCode {
@@ -207,19 +629,16 @@ fn main() -> ExitCode {
 
     let reader = Reader::stdin();
 
-    let result = match operation {
+    let result = match &operation {
         Operation::Lowercase => lowercase(reader),
         Operation::Uppercase => uppercase(reader),
         Operation::NoSpaces => no_spaces(reader),
         Operation::Slugify => slugify_input(reader),
-        Operation::Csv => csv(reader),
+        Operation::Csv(options) => csv(reader, options),
     };
 
     match result {
-        Ok(output) => {
-            print!("{}", output);
-            ExitCode::SUCCESS
-        }
+        Ok(output) => write_output(&output),
         Err(error) => {
             eprintln!("Error while executing {}: {}", operation, error);
             ExitCode::FAILURE
@@ -227,8 +646,33 @@ fn main() -> ExitCode {
     }
 }
 
+/// Writes `output` to stdout, treating a broken pipe (e.g. the consumer end
+/// of a pipeline like `| head` closing early) as a successful exit rather
+/// than an error, mirroring the behavior of common Unix text utilities.
+fn write_output(output: &str) -> ExitCode {
+    match io::stdout().write_all(output.as_bytes()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(ref error) if error.kind() == io::ErrorKind::BrokenPipe => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("Error while writing output: {}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
+
 fn parse_args() -> Result<Operation, String> {
     let args: Vec<String> = env::args().collect();
+    if args.len() < 2 {
+        return Err(format!(
+            "Got incorrect number of arguments: {}.",
+            args.len()
+        ));
+    }
+
+    if args[1] == "csv" {
+        return CsvOptions::parse(&args[2..]).map(Operation::Csv);
+    }
+
     if args.len() != 2 {
         return Err(format!(
             "Got incorrect number of arguments: {}.",
@@ -238,34 +682,55 @@ fn parse_args() -> Result<Operation, String> {
     Operation::try_from(args[1].as_str())
 }
 
-fn lowercase(mut reader: Reader<'_>) -> Result<String, Box<dyn Error>> {
-    match reader.next() {
-        Some(line) => Ok(line.to_lowercase()),
-        None => Err(Box::new(SimpleError::from_str("Empty input."))),
-    }
+fn lowercase(reader: Reader<'_>) -> Result<String, Box<dyn Error>> {
+    transform_lines(reader, |line| line.to_lowercase())
 }
 
-fn uppercase(mut reader: Reader<'_>) -> Result<String, Box<dyn Error>> {
-    match reader.next() {
-        Some(line) => Ok(line.to_uppercase()),
-        None => Err(Box::new(SimpleError::from_str("Empty input."))),
-    }
+fn uppercase(reader: Reader<'_>) -> Result<String, Box<dyn Error>> {
+    transform_lines(reader, |line| line.to_uppercase())
 }
 
-fn no_spaces(mut reader: Reader<'_>) -> Result<String, Box<dyn Error>> {
-    match reader.next() {
-        Some(line) => Ok(line.replace(' ', "")),
-        None => Err(Box::new(SimpleError::from_str("Empty input."))),
-    }
+fn no_spaces(reader: Reader<'_>) -> Result<String, Box<dyn Error>> {
+    transform_lines(reader, |line| line.replace(' ', ""))
+}
+
+fn slugify_input(reader: Reader<'_>) -> Result<String, Box<dyn Error>> {
+    transform_lines(reader, |line| slugify(line))
 }
 
-fn slugify_input(mut reader: Reader<'_>) -> Result<String, Box<dyn Error>> {
-    match reader.next() {
-        Some(line) => Ok(slugify(line)),
-        None => Err(Box::new(SimpleError::from_str("Empty input."))),
+/// Applies `transform` to every line of `reader`, preserving newlines, so
+/// the text operations behave like a real Unix filter over multi-line
+/// input rather than only looking at the first line.
+fn transform_lines(
+    reader: Reader<'_>,
+    transform: impl Fn(&str) -> String,
+) -> Result<String, Box<dyn Error>> {
+    let mut output = String::new();
+    for line in reader {
+        output.push_str(&transform(&line));
+        output.push('\n');
+    }
+
+    if output.is_empty() {
+        return Err(Box::new(SimpleError::from_str("Empty input.")));
     }
+
+    Ok(output)
 }
 
-fn csv(mut reader: Reader<'_>) -> Result<String, Box<dyn Error>> {
-    Ok(Csv::from_reader(&mut reader)?.to_string())
+fn csv(mut reader: Reader<'_>, options: &CsvOptions) -> Result<String, Box<dyn Error>> {
+    let mut table = Csv::from_reader(&mut reader, options.delimiter)?;
+
+    if let Some(predicate) = &options.filter {
+        table.filter(predicate)?;
+    }
+    if let Some(keys) = &options.sort {
+        table.sort_by(keys)?;
+    }
+    let table = match &options.select {
+        Some(specs) => table.select(specs)?,
+        None => table,
+    };
+
+    Ok(table.render(&options.format, options.delimiter))
 }